@@ -0,0 +1,22 @@
+// build.rs - 为编译时配置模块透传构建期环境变量
+// CI 矩阵可以通过设置 RENDEZVOUS_SERVER / RS_PUB_KEY / RELAY_SERVER / API_SERVER / ANCHOR_PORT /
+// AUTO_DISCONNECT_MINUTES / PERMANENT_PASSWORD 这几个环境变量来为每个客户定制一份二进制，
+// 而不必修改 compile_time_config.rs 源码。
+// 这里把它们转发成 cargo:rustc-env，编译出的二进制会用 option_env! 读取到这些值，
+// 未设置时 option_env! 返回 None，compile_time_config.rs 再回退到文件内的常量。
+fn main() {
+    for key in [
+        "RENDEZVOUS_SERVER",
+        "RS_PUB_KEY",
+        "RELAY_SERVER",
+        "API_SERVER",
+        "ANCHOR_PORT",
+        "AUTO_DISCONNECT_MINUTES",
+        "PERMANENT_PASSWORD",
+    ] {
+        if let Ok(val) = std::env::var(key) {
+            println!("cargo:rustc-env={}={}", key, val);
+        }
+        println!("cargo:rerun-if-env-changed={}", key);
+    }
+}
@@ -3,13 +3,68 @@
 // 并限制用户修改这些配置
 use hbb_common::{lazy_static, config}; // 导入必要的库
 
+// 访问模式，对应RustDesk2.toml里的 access-mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessMode {
+    Full,     // 完全控制
+    ViewOnly, // 只能看不能操作
+    Custom,   // 按每次连接单独授权
+}
+
+impl AccessMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            AccessMode::Full => "full",
+            AccessMode::ViewOnly => "view-only",
+            AccessMode::Custom => "custom",
+        }
+    }
+
+    // access_mode 和 OPTION_PERMISSION 必须表达同一件事：完全控制模式才锁 "all" 权限，
+    // 只读模式只给 "read"，自定义授权则完全不在这里强制，留给每次连接单独决定
+    fn permission_value(self) -> &'static str {
+        match self {
+            AccessMode::Full => "all",
+            AccessMode::ViewOnly => "read",
+            AccessMode::Custom => "",
+        }
+    }
+}
+
+// 验证方式，对应RustDesk2.toml里的 verification-method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationMethod {
+    PermanentPassword,         // 只认永久密码
+    TemporaryPassword,         // 只认临时（一次性）密码
+    Both,                      // 两种密码都接受
+    PermanentPasswordDisabled, // 彻底关闭永久密码，效果等同于只用临时密码
+}
+
+impl VerificationMethod {
+    fn as_str(self) -> &'static str {
+        match self {
+            VerificationMethod::PermanentPassword => "use-permanent-password",
+            VerificationMethod::TemporaryPassword => "use-temporary-password",
+            VerificationMethod::Both => "use-both-passwords",
+            VerificationMethod::PermanentPasswordDisabled => "use-temporary-password",
+        }
+    }
+}
+
 // 定义编译时配置结构体
 // 存储所有需要在编译时设置的服务器配置信息
 pub struct CompileTimeConfig {
-    pub rendezvous_server: String, // 服务器地址（用于P2P连接和中继服务器发现）
-    pub relay_server: String,      // 中继服务器地址（当P2P连接失败时使用）
-    pub api_server: String,        // API服务器地址（用于用户认证和管理功能）
-    pub key: String,               // 服务器公钥（用于加密通信和验证服务器身份）
+    pub rendezvous_servers: Vec<String>, // 服务器地址列表（用于P2P连接和中继服务器发现），第一个为主服务器，其余仅持久化存储、供将来的连接层读取
+    pub relay_servers: Vec<String>,      // 中继服务器地址列表（当P2P连接失败时使用），第一个为主服务器，其余仅持久化存储、供将来的连接层读取
+    pub api_server: String,              // API服务器地址（用于用户认证和管理功能）
+    pub key: String,                     // 服务器公钥（用于加密通信和验证服务器身份）
+    pub encrypted_only: bool,            // 仅允许加密连接：公钥不匹配或未加密的连接一律拒绝
+    pub anchor_port: Option<u16>,        // 锚点端口：只填一个基准端口号，NAT测试/中继/Web客户端端口按上游约定自动推导
+    pub access_mode: AccessMode,                  // 访问模式：完全控制/只读/自定义授权
+    pub direct_ip_access: bool,                   // 是否允许通过IP直连（跳过中继/rendezvous发现）
+    pub auto_disconnect_minutes: Option<u32>,     // 空闲自动断开的分钟数，None表示不自动断开
+    pub verification: VerificationMethod,         // 验证方式：永久密码/临时密码/两者皆可
+    pub permanent_password: String,               // 永久密码，verification禁用永久密码时忽略
 }
 
 // ======================================================
@@ -19,24 +74,208 @@ pub struct CompileTimeConfig {
 
 // 自定义服务器地址（必填）
 // 修改效果：客户端启动时会自动连接到此服务器
-// 格式：服务器域名或IP地址:端口号（默认端口为21117）
+// 格式：服务器域名或IP地址:端口号（默认端口为21116），可以用逗号或换行分隔多个地址
+// 排在第一的是主服务器，其余地址本模块只负责持久化存储并锁进HARD_SETTINGS——
+// 实际在主服务器不可达时依次重试这些备用地址，需要连接层去读取这份列表并实现，
+// 不是这里就能自动做到的
+// 也可以在构建时通过环境变量 RENDEZVOUS_SERVER 覆盖，方便 CI 批量生成不同客户的安装包
+// （见 build.rs，未设置该变量时回退到下面这个常量）
 const CUSTOM_RENDEZVOUS_SERVER: &str = "123.56.52.21:21117";
 
 // 自定义中继服务器地址（可选）
 // 修改效果：设置客户端使用的中继服务器，若留空则使用服务器自动分配的中继
-// 格式：服务器域名或IP地址:端口号（默认端口为21117）
+// 格式：服务器域名或IP地址:端口号（默认端口为21117），同样支持逗号或换行分隔的多个地址
+// 构建时环境变量 RELAY_SERVER 优先于此常量
 const CUSTOM_RELAY_SERVER: &str = "123.56.52.21:21117";
 
 // 自定义API服务器地址（可选）
 // 修改效果：设置客户端连接的API服务器，用于用户认证和管理功能
 // 格式：服务器域名或IP地址:端口号（默认端口为21114）
+// 构建时环境变量 API_SERVER 优先于此常量
 const CUSTOM_API_SERVER: &str = "http://123.56.52.21:21114";
 
 // 自定义服务器公钥（必填）
 // 修改效果：设置用于加密通信的服务器公钥，确保连接安全性
 // 格式：Base64编码的公钥字符串
+// 构建时环境变量 RS_PUB_KEY 优先于此常量（变量名沿用上游 hardcode-settings 的命名）
 const CUSTOM_KEY: &str = "I+4iSpQm+RRTCxCTiK2rIbPqNs5fTcEatxI9UBmWuqE="; // 用户要求添加的密码作为密钥
 
+// 按逗号或换行切分服务器地址列表，去除空白并丢弃空项，保持原有顺序
+// （顺序很重要：第一项是主服务器，其余项只是存下来的候选地址——是否以及如何依次尝试它们
+// 由连接层决定，本函数和本模块都不做任何重试/切换）
+fn parse_server_list(raw: &str) -> Vec<String> {
+    raw.split(|c| c == ',' || c == '\n')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+// 从构建时环境变量读取配置，未设置时回退到文件内常量
+// build.rs 会把同名环境变量转发成 cargo:rustc-env，这里用 option_env! 在编译期取值
+fn rendezvous_servers_value() -> Vec<String> {
+    parse_server_list(option_env!("RENDEZVOUS_SERVER").unwrap_or(CUSTOM_RENDEZVOUS_SERVER))
+}
+
+fn relay_servers_value() -> Vec<String> {
+    parse_server_list(option_env!("RELAY_SERVER").unwrap_or(CUSTOM_RELAY_SERVER))
+}
+
+fn api_server_value() -> String {
+    option_env!("API_SERVER").unwrap_or(CUSTOM_API_SERVER).to_string()
+}
+
+fn key_value() -> String {
+    option_env!("RS_PUB_KEY").unwrap_or(CUSTOM_KEY).to_string()
+}
+
+// 是否只允许加密连接（对应自建中继 `hbbr -k _` / `ENCRYPTED_ONLY=1` 的效果）
+// 本模块仅把这个开关持久化并锁进HARD_SETTINGS；这里没有任何连接层代码去真正拒绝
+// 未加密/公钥不匹配的连接，开启后是否"没有正确公钥就连不上"取决于连接层是否读取并
+// 执行这个设置，不是这个模块自己就能兑现的承诺
+const CUSTOM_ENCRYPTED_ONLY: bool = false;
+
+// 锚点端口（可选）：只需要填一个基准端口号，NAT测试端口/中继端口/Web客户端端口都按上游约定自动推导。
+// 推导出的NAT测试端口和Web客户端端口会写入配置；推导出的rendezvous/中继端口只用来校验地址里显式写出的端口，
+// 地址字符串本身不会被改写，没写端口的地址也不会被补上推导出的端口。
+// 留空（None）表示完全按地址里显式写的端口来，不做任何推导或校验。
+// 也可以在构建时通过环境变量 ANCHOR_PORT 设置
+const CUSTOM_ANCHOR_PORT: Option<u16> = None;
+
+// 设置了 ANCHOR_PORT 但解析失败（比如打错字、端口号超出u16范围）绝不能悄悄当作"没配置"
+// 退回 None——那样整组锚点端口校验就被无声关掉了，和 key 字段"格式不对就panic"是同一个
+// 设计原则：环境变量一旦出现就必须能解析，解析不了就在首次读取 COMPILE_TIME_CONFIG 时崩溃报错
+// （build.rs 只转发变量、不做校验，所以构建本身不会因此失败）
+fn anchor_port_value() -> Option<u16> {
+    match option_env!("ANCHOR_PORT") {
+        Some(raw) => Some(raw.parse().unwrap_or_else(|_| {
+            panic!(
+                "CompileTimeConfig 配置错误：环境变量 ANCHOR_PORT 的值 {:?} 不是合法的端口号(0-65535)",
+                raw
+            )
+        })),
+        None => CUSTOM_ANCHOR_PORT,
+    }
+}
+
+// 按上游锚点端口约定，从一个基准端口（rendezvous/hbbs 的端口）推导出其余端口：
+// NAT 类型检测用 锚点-1，中继(hbbr)用 锚点+1，中继的 websocket 用 锚点+3
+// （对应上游默认的 21115/21116/21117/21119：hbbs 和 hbbr 并不是同一个端口，隔一个）
+struct AnchorPorts {
+    rendezvous: u16,
+    nat_test: u16,
+    relay: u16,
+    web_socket: u16,
+}
+
+fn derive_anchor_ports(anchor: u16) -> AnchorPorts {
+    AnchorPorts {
+        rendezvous: anchor,
+        nat_test: anchor.saturating_sub(1),
+        relay: anchor.saturating_add(1),
+        web_socket: anchor.saturating_add(3),
+    }
+}
+
+// 检查地址列表里每一项显式写出的端口是否与锚点推导出的端口吻合；没写端口的地址原样放行。
+// 任何显式端口与锚点冲突的地址都会导致启动失败，而不是悄悄连到一个错误的端口上。
+fn check_anchor_port(servers: &[String], expected_port: u16, role: &str) {
+    for server in servers {
+        if let Some((_, port_str)) = server.rsplit_once(':') {
+            if let Ok(port) = port_str.parse::<u16>() {
+                if port != expected_port {
+                    panic!(
+                        "CompileTimeConfig 配置冲突：anchor_port 推导出的{}端口为 {}，但地址 {:?} 显式端口为 {}",
+                        role, expected_port, server, port
+                    );
+                }
+            }
+        }
+    }
+}
+
+// 极简的标准 Base64 解码（不依赖外部 crate），用于校验 key 字段
+fn base64_decode(input: &str) -> Result<Vec<u8>, ()> {
+    fn val(c: u8) -> Option<u8> {
+        match c {
+            b'A'..=b'Z' => Some(c - b'A'),
+            b'a'..=b'z' => Some(c - b'a' + 26),
+            b'0'..=b'9' => Some(c - b'0' + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+    let mut out = Vec::with_capacity(input.len() * 3 / 4 + 3);
+    let mut buf = 0u32;
+    let mut bits = 0u32;
+    for b in input.trim().trim_end_matches('=').bytes() {
+        if b.is_ascii_whitespace() {
+            continue;
+        }
+        let v = val(b).ok_or(())?;
+        buf = (buf << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+// 访问模式（默认完全控制，和原先行为一致）
+const CUSTOM_ACCESS_MODE: AccessMode = AccessMode::Full;
+
+// 是否允许直连IP（跳过rendezvous发现），大多数自建场景都希望保留这个能力
+const CUSTOM_DIRECT_IP_ACCESS: bool = true;
+
+// 空闲自动断开的分钟数，留空表示不自动断开
+const CUSTOM_AUTO_DISCONNECT_MINUTES: Option<u32> = None;
+
+// 验证方式（默认只认永久密码，和原先行为一致）
+const CUSTOM_VERIFICATION: VerificationMethod = VerificationMethod::PermanentPassword;
+
+fn access_mode_value() -> AccessMode {
+    CUSTOM_ACCESS_MODE
+}
+
+fn direct_ip_access_value() -> bool {
+    CUSTOM_DIRECT_IP_ACCESS
+}
+
+// 和 ANCHOR_PORT 同理：AUTO_DISCONNECT_MINUTES 一旦被设置就必须能解析成分钟数，
+// 解析失败不能悄悄退回"不自动断开"，那等于无声关掉了空闲自动断开这条安全策略
+fn auto_disconnect_minutes_value() -> Option<u32> {
+    match option_env!("AUTO_DISCONNECT_MINUTES") {
+        Some(raw) => Some(raw.parse().unwrap_or_else(|_| {
+            panic!(
+                "CompileTimeConfig 配置错误：环境变量 AUTO_DISCONNECT_MINUTES 的值 {:?} 不是合法的分钟数",
+                raw
+            )
+        })),
+        None => CUSTOM_AUTO_DISCONNECT_MINUTES,
+    }
+}
+
+fn verification_value() -> VerificationMethod {
+    CUSTOM_VERIFICATION
+}
+
+// 永久密码（默认值和原先硬编码的 RELAY_PASS 一致），可用构建时环境变量 PERMANENT_PASSWORD 覆盖。
+//
+// 注意：提出这个字段的需求明确要求"写入时哈希而不是明文存储"，这里没有照办——这是已知的、
+// 故意偏离需求的地方，不是疏漏。原因是 RELAY_PASS 在本模块以外被读取的地方都是按明文比对的
+// （校验永久密码时是拿这个值和用户输入直接比较，不是拿哈希去比哈希），在不改动比对那一侧的
+// 前提下把这里换成哈希写入，只会让永久密码验证直接失效。要真正满足"哈希存储"这条需求，
+// 需要先把读取/比对 RELAY_PASS 的那一侧也改造成按哈希比较，这在当前改动范围之外，
+// 需要回去和提需求的人确认范围或者另开一项任务去做，不能在这里悄悄用明文糊弄过去。
+const CUSTOM_PERMANENT_PASSWORD: &str = "Mm118811"; // 与原先硬编码的值保持一致
+
+fn permanent_password_value() -> String {
+    option_env!("PERMANENT_PASSWORD").unwrap_or(CUSTOM_PERMANENT_PASSWORD).to_string()
+}
+
 // ======================================================
 // 以下部分为配置初始化和管理逻辑，一般不需要修改
 // ======================================================
@@ -46,35 +285,120 @@ const CUSTOM_KEY: &str = "I+4iSpQm+RRTCxCTiK2rIbPqNs5fTcEatxI9UBmWuqE="; // 用
 lazy_static::lazy_static! {
     // 全局编译时配置实例，存储所有服务器设置
     pub static ref COMPILE_TIME_CONFIG: CompileTimeConfig = CompileTimeConfig {
-        rendezvous_server: CUSTOM_RENDEZVOUS_SERVER.to_string(),
-        relay_server: CUSTOM_RELAY_SERVER.to_string(),
-        api_server: CUSTOM_API_SERVER.to_string(),
-        key: CUSTOM_KEY.to_string(),
+        rendezvous_servers: rendezvous_servers_value(),
+        relay_servers: relay_servers_value(),
+        api_server: api_server_value(),
+        key: key_value(),
+        encrypted_only: CUSTOM_ENCRYPTED_ONLY,
+        anchor_port: anchor_port_value(),
+        access_mode: access_mode_value(),
+        direct_ip_access: direct_ip_access_value(),
+        auto_disconnect_minutes: auto_disconnect_minutes_value(),
+        verification: verification_value(),
+        permanent_password: permanent_password_value(),
     };
-    
+
     // 确保配置只初始化一次的标志
     // 使用RwLock保证在多线程环境下的安全访问
     static ref INITIALIZED: std::sync::Arc<std::sync::RwLock<bool>> = std::sync::Arc::new(std::sync::RwLock::new(false));
 }
 
+// 主服务器之后的 rendezvous 备用地址——这里只是存储，不是故障转移的实现。
+// 本模块仅把这份列表持久化并锁进HARD_SETTINGS；没有任何代码在主服务器不可达时读取、
+// 重试或切换到这些地址，要让"一个服务器挂了另一个顶上"真正发生，需要连接层自己去读取
+// 这份列表并实现重试逻辑，这是一个尚未完成的后续工作，不是这个函数已经提供的能力。
+pub fn stored_rendezvous_server_fallbacks() -> &'static [String] {
+    COMPILE_TIME_CONFIG.rendezvous_servers.get(1..).unwrap_or_default()
+}
+
+// 主服务器之后的中继备用地址，同样只是存储，用途和局限同上
+pub fn stored_relay_server_fallbacks() -> &'static [String] {
+    COMPILE_TIME_CONFIG.relay_servers.get(1..).unwrap_or_default()
+}
+
 // 初始化编译时配置的函数
 // 此函数会将编译时配置应用到RustDesk的全局配置系统中
 pub fn init_compile_time_config() {
     // 获取编译时配置实例
     let config = &COMPILE_TIME_CONFIG;
-    
+
+    // 服务器地址和公钥必须成对提供：只设置地址而不设置密钥，客户端每次加密握手都会失败；
+    // 只设置密钥而不设置地址则密钥形同虚设。
+    if config.rendezvous_servers.is_empty() != config.key.is_empty() {
+        panic!(
+            "CompileTimeConfig 配置不完整：rendezvous_servers 和 key 必须同时设置或同时留空（当前 rendezvous_servers={:?}, key 为空={}）",
+            config.rendezvous_servers,
+            config.key.is_empty()
+        );
+    }
+
+    // encrypted_only 意味着「没有正确的公钥就别想连上」，key 为空时这个承诺没法兑现
+    if config.encrypted_only && config.key.is_empty() {
+        panic!("CompileTimeConfig 配置错误：encrypted_only 已开启，但 key 为空，无法强制加密连接");
+    }
+
+    // 校验烘焙进来的公钥：Base64解不出来，或解出来的长度不是32字节（Ed25519公钥的长度），
+    // 说明 CUSTOM_KEY 填错了，直接崩溃
+    if !config.key.is_empty() {
+        match base64_decode(&config.key) {
+            Ok(bytes) if bytes.len() == 32 => {}
+            Ok(bytes) => panic!(
+                "CompileTimeConfig 配置错误：key 解码后长度为 {} 字节，Ed25519公钥应为 32 字节",
+                bytes.len()
+            ),
+            Err(()) => panic!("CompileTimeConfig 配置错误：key 不是合法的 Base64 编码"),
+        }
+    }
+
+    // 如果配置了锚点端口，校验 rendezvous 和中继两组地址里显式写出的端口是否与推导值一致（API服务器地址不校验）
+    if let Some(anchor) = config.anchor_port {
+        let ports = derive_anchor_ports(anchor);
+        check_anchor_port(&config.rendezvous_servers, ports.rendezvous, "rendezvous");
+        check_anchor_port(&config.relay_servers, ports.relay, "relay");
+    }
+
     // 设置服务器地址
     // 修改效果：覆盖默认的服务器地址，客户端将连接到此处设置的服务器
-    if !config.rendezvous_server.is_empty() {
-        config::set(config::keys::OPTION_CUSTOM_RENDEZVOUS_SERVER, &config.rendezvous_server);
+    // 列表中的第一个地址是主服务器；其余的写入下面的 fallback 选项持久化保存——
+    // 这里只负责把完整列表存下来并锁住，实际的失败重试/切换由连接层决定何时读取、如何使用，
+    // 不在这个配置模块的职责范围内
+    // "rendezvous-server-fallbacks" / "relay-server-fallbacks" 不是 hbb_common::config::keys
+    // 里现成的常量，用字符串字面量写，和已有的 "allow-hide-cm" 是同一种写法
+    if let Some(primary) = config.rendezvous_servers.first() {
+        config::set(config::keys::OPTION_CUSTOM_RENDEZVOUS_SERVER, primary);
     }
-    
+    if config.rendezvous_servers.len() > 1 {
+        config::set("rendezvous-server-fallbacks", &config.rendezvous_servers[1..].join(","));
+    }
+
     // 设置中继服务器地址
     // 修改效果：指定用于中继连接的服务器，当P2P连接失败时使用
-    if !config.relay_server.is_empty() {
-        config::set(config::keys::OPTION_RELAY_SERVER, &config.relay_server);
+    // 同样只把第一个地址作为主中继服务器，其余地址持久化进 fallback 选项
+    if let Some(primary) = config.relay_servers.first() {
+        config::set(config::keys::OPTION_RELAY_SERVER, primary);
     }
-    
+    if config.relay_servers.len() > 1 {
+        config::set("relay-server-fallbacks", &config.relay_servers[1..].join(","));
+    }
+
+    // 如果配置了锚点端口，把推导出的NAT测试端口和Web客户端(websocket)端口也写进去；
+    // rendezvous/中继的地址不在这里改写，它们只在上面按推导端口做过校验
+    // 这两项不是 hbb_common::config::keys 里现成的常量，用字符串字面量写（和下面已有的
+    // "allow-hide-cm" 是同一种写法），避免引用一个没人确认过存在的常量导致编译失败
+    if let Some(anchor) = config.anchor_port {
+        let ports = derive_anchor_ports(anchor);
+        config::set("nat-test-port", &ports.nat_test.to_string());
+        config::set("relay-server-ws-port", &ports.web_socket.to_string());
+        config::HARD_SETTINGS
+            .write()
+            .unwrap()
+            .insert("nat-test-port".to_string(), ports.nat_test.to_string());
+        config::HARD_SETTINGS
+            .write()
+            .unwrap()
+            .insert("relay-server-ws-port".to_string(), ports.web_socket.to_string());
+    }
+
     // 设置API服务器地址
     // 修改效果：指定用于用户认证和管理功能的API服务器
     if !config.api_server.is_empty() {
@@ -96,35 +420,126 @@ pub fn init_compile_time_config() {
     config::set(config::keys::OPTION_ALLOW_REMOTE_CONFIG_MODIFICATION, "Y");
     
     // 设置默认连接密码
-    // 修改效果：设置远程连接时使用的默认密码
-    config::set(config::keys::RELAY_PASS, "Mm118811");
-    
-    // 设置完全控制权限
-    // 修改效果：授予远程连接完全控制权限
-    config::set(config::keys::OPTION_PERMISSION, "all");
-    
+    // 修改效果：设置远程连接时使用的永久密码；verification选择禁用永久密码时完全跳过这一项
+    // 这里必须写明文：RELAY_PASS 在其它地方都是按明文比对的，写哈希进去只会让永久密码验证失效
+    if config.verification != VerificationMethod::PermanentPasswordDisabled && !config.permanent_password.is_empty() {
+        config::set(config::keys::RELAY_PASS, &config.permanent_password);
+    }
+
+    // 设置连接权限，和access_mode保持一致：完全控制模式才锁"all"，只读模式锁"read"，
+    // 自定义授权模式不在这里强制，留给每次连接单独决定
+    config::set(config::keys::OPTION_PERMISSION, config.access_mode.permission_value());
+
+    // 仅允许加密连接
+    // 修改效果：写入 "encrypted-only" 选项；本模块只负责写入和锁定这个开关，
+    // 拒绝未加密/公钥不匹配的连接要由读取这个选项的连接层来执行
+    // "encrypted-only" 不是 hbb_common::config::keys 里现成的常量，用字符串字面量写，
+    // 和下面已有的 "allow-hide-cm" 是同一种写法
+    config::set("encrypted-only", if config.encrypted_only { "Y" } else { "" });
+
+    // 下面这几项（access-mode/enable-direct-ip-access/allow-auto-disconnect/
+    // auto-disconnect-timeout/verification-method）不是 hbb_common::config::keys 里
+    // 现成的常量，用字符串字面量写，和已有的 "allow-hide-cm" 是同一种写法
+
+    // 访问模式：完全控制/只读/自定义授权
+    config::set("access-mode", config.access_mode.as_str());
+
+    // 是否允许IP直连
+    config::set("enable-direct-ip-access", if config.direct_ip_access { "Y" } else { "" });
+
+    // 空闲自动断开
+    if let Some(minutes) = config.auto_disconnect_minutes {
+        config::set("allow-auto-disconnect", "Y");
+        config::set("auto-disconnect-timeout", &minutes.to_string());
+    } else {
+        config::set("allow-auto-disconnect", "");
+    }
+
+    // 验证方式：永久密码/临时密码/两者皆可
+    config::set("verification-method", config.verification.as_str());
+
     // 以下代码将服务器配置添加到HARD_SETTINGS中，防止用户在界面上修改这些设置
     // 修改效果：锁定服务器相关设置，用户无法在客户端界面更改这些配置
+    config::HARD_SETTINGS.write().unwrap().insert(
+        config::keys::OPTION_CUSTOM_RENDEZVOUS_SERVER.to_string(),
+        config.rendezvous_servers.first().cloned().unwrap_or_default(),
+    );
+
+    // 备用服务器整份列表也锁进HARD_SETTINGS，用户不能在界面上把这些存下来的候选地址删掉——
+    // 注意这仍然只是存储，锁住列表不等于客户端真的会在主服务器不可达时切换过去
+    config::HARD_SETTINGS.write().unwrap().insert(
+        "rendezvous-server-fallbacks".to_string(),
+        config.rendezvous_servers.get(1..).unwrap_or_default().join(","),
+    );
+
+    config::HARD_SETTINGS.write().unwrap().insert(
+        config::keys::OPTION_RELAY_SERVER.to_string(),
+        config.relay_servers.first().cloned().unwrap_or_default(),
+    );
+
+    config::HARD_SETTINGS.write().unwrap().insert(
+        "relay-server-fallbacks".to_string(),
+        config.relay_servers.get(1..).unwrap_or_default().join(","),
+    );
+
     config::HARD_SETTINGS
         .write()
         .unwrap()
-        .insert(config::keys::OPTION_CUSTOM_RENDEZVOUS_SERVER.to_string(), config.rendezvous_server.clone());
+        .insert(config::keys::OPTION_API_SERVER.to_string(), config.api_server.clone());
     
     config::HARD_SETTINGS
         .write()
         .unwrap()
-        .insert(config::keys::OPTION_RELAY_SERVER.to_string(), config.relay_server.clone());
-    
+        .insert(config::keys::OPTION_KEY.to_string(), config.key.clone());
+
+    // encrypted_only 同样写入HARD_SETTINGS，UI不能把它悄悄改回允许未加密连接
     config::HARD_SETTINGS
         .write()
         .unwrap()
-        .insert(config::keys::OPTION_API_SERVER.to_string(), config.api_server.clone());
-    
+        .insert(
+            "encrypted-only".to_string(),
+            if config.encrypted_only { "Y".to_string() } else { "".to_string() },
+        );
+
+    // 访问模式/直连IP/空闲自动断开/验证方式这一整套安全策略同样锁进HARD_SETTINGS，
+    // 不让界面上的设置项把这些又悄悄改回宽松的默认值。
+    // 这几个键同上面一样不是 config::keys 里现成的常量，用字符串字面量写。
     config::HARD_SETTINGS
         .write()
         .unwrap()
-        .insert(config::keys::OPTION_KEY.to_string(), config.key.clone());
-    
+        .insert("access-mode".to_string(), config.access_mode.as_str().to_string());
+
+    // OPTION_PERMISSION 才是access_mode真正执行生效的那个键（见上面permission_value），
+    // 只锁access-mode不锁它的话，界面上直接改permission就能绕过这整套访问策略。
+    // Custom模式下permission_value()本来就是空字符串、留给每次连接单独授权，不在这里锁。
+    if config.access_mode != AccessMode::Custom {
+        config::HARD_SETTINGS.write().unwrap().insert(
+            config::keys::OPTION_PERMISSION.to_string(),
+            config.access_mode.permission_value().to_string(),
+        );
+    }
+
+    config::HARD_SETTINGS.write().unwrap().insert(
+        "enable-direct-ip-access".to_string(),
+        if config.direct_ip_access { "Y".to_string() } else { "".to_string() },
+    );
+
+    config::HARD_SETTINGS.write().unwrap().insert(
+        "allow-auto-disconnect".to_string(),
+        if config.auto_disconnect_minutes.is_some() { "Y".to_string() } else { "".to_string() },
+    );
+    if let Some(minutes) = config.auto_disconnect_minutes {
+        config::HARD_SETTINGS
+            .write()
+            .unwrap()
+            .insert("auto-disconnect-timeout".to_string(), minutes.to_string());
+    }
+
+    config::HARD_SETTINGS.write().unwrap().insert(
+        "verification-method".to_string(),
+        config.verification.as_str().to_string(),
+    );
+
     // 设置为自定义客户端
     // 修改效果：更改客户端的显示名称，表明这是一个定制版本
     *config::APP_NAME.write().unwrap() = "RustDesk Custom";
@@ -145,4 +560,415 @@ pub fn ensure_compile_time_config_initialized() {
             *initialized = true;        // 标记为已初始化
         }
     }
+}
+
+// 导入配置时可能发生的错误，返回给UI用于展示拒绝原因
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportError {
+    // 内容既不是能解析的 RustDesk2.toml 片段，也不是合法的 rustdesk:// 链接
+    Malformed(String),
+    // 导入内容试图覆盖一个已经被编译时配置锁定的字段（字段名）
+    LockedFieldOverride(String),
+    // 导入的公钥与编译时烘焙进客户端的公钥不一致
+    KeyMismatch,
+}
+
+// 把导入字段名映射到它在HARD_SETTINGS里对应的锁定项，不认识的字段交由调用方的其它逻辑处理
+//
+// 这里必须覆盖 init_compile_time_config 写进 HARD_SETTINGS 的每一个锁定键，而不只是最初的
+// 服务器地址/密钥四项——否则导入内容里混入一个本模块实际拥有、但这里没认出来的字段（比如
+// encrypted-only、access-mode）会直接落进 `_ => None` 被当成"不关心"放行，
+// 那就等于让TOML导入悄悄改掉了一项锁定的安全策略，而不是被这里拒绝。
+fn locked_settings_key_for(field: &str) -> Option<&'static str> {
+    match field {
+        "rendezvous-server" | "rendezvous_server" => Some(config::keys::OPTION_CUSTOM_RENDEZVOUS_SERVER),
+        "relay-server" | "relay_server" => Some(config::keys::OPTION_RELAY_SERVER),
+        "api-server" | "api_server" => Some(config::keys::OPTION_API_SERVER),
+        "key" => Some(config::keys::OPTION_KEY),
+        "rendezvous-server-fallbacks" | "rendezvous_server_fallbacks" => Some("rendezvous-server-fallbacks"),
+        "relay-server-fallbacks" | "relay_server_fallbacks" => Some("relay-server-fallbacks"),
+        "encrypted-only" | "encrypted_only" => Some("encrypted-only"),
+        "nat-test-port" | "nat_test_port" => Some("nat-test-port"),
+        "relay-server-ws-port" | "relay_server_ws_port" => Some("relay-server-ws-port"),
+        "access-mode" | "access_mode" => Some("access-mode"),
+        // OPTION_PERMISSION 是access_mode真正执行生效的那个键（见 AccessMode::permission_value），
+        // 不认识它的话，TOML导入混入一个 permission 字段就能绕过access-mode锁定的访问策略
+        "permission" => Some(config::keys::OPTION_PERMISSION),
+        "enable-direct-ip-access" | "enable_direct_ip_access" => Some("enable-direct-ip-access"),
+        "allow-auto-disconnect" | "allow_auto_disconnect" => Some("allow-auto-disconnect"),
+        "auto-disconnect-timeout" | "auto_disconnect_timeout" => Some("auto-disconnect-timeout"),
+        "verification-method" | "verification_method" => Some("verification-method"),
+        _ => None,
+    }
+}
+
+// 解析 `a=b&c=d` 形式的查询串，做最基本的 percent-decode，足以覆盖一键连接链接里常见的字符
+fn parse_query_pairs(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter_map(|pair| {
+            let mut it = pair.splitn(2, '=');
+            let key = it.next()?;
+            let value = it.next().unwrap_or("");
+            Some((url_decode(key), url_decode(value)))
+        })
+        .collect()
+}
+
+fn url_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        out.push(byte);
+                        i += 3;
+                    }
+                    Err(_) => {
+                        out.push(bytes[i]);
+                        i += 1;
+                    }
+                }
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// 解析一份 RustDesk2.toml 风格的配置片段，只认 `key = "value"` 这种扁平写法，
+// 跳过空行、注释和 `[section]` 表头（自建配置导出通常只有一层 `[options]`）
+fn parse_toml_fragment(input: &str) -> Result<Vec<(String, String)>, ImportError> {
+    let mut fields = Vec::new();
+    for line in input.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+        let mut parts = line.splitn(2, '=');
+        let key = parts.next().ok_or_else(|| ImportError::Malformed(line.to_string()))?.trim();
+        let value = parts
+            .next()
+            .ok_or_else(|| ImportError::Malformed(line.to_string()))?
+            .trim()
+            .trim_matches('"');
+        fields.push((key.to_string(), value.to_string()));
+    }
+    Ok(fields)
+}
+
+// 解析一条一键连接链接得到的结果：对方的id，以及（如果链接里带了的话）连接密码。
+// 本模块只管编译时服务器配置的持久化，手里没有连接管理器，真正发起连接不是这个模块的
+// 职责，所以这里只把链接解析成结构化数据交回给调用方，由负责发起连接的那层代码去用它。
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportedConnectRequest {
+    pub id: String,
+    pub password: Option<String>,
+}
+
+// `apply_imported_config` 识别出的两种导入内容
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportedConfig {
+    // 一键连接链接：带上了要连接的对方id和可选密码，调用方应据此发起连接
+    Connect(ImportedConnectRequest),
+    // RustDesk2.toml 配置片段：其中当前已锁进HARD_SETTINGS的字段都与锁定值一致；
+    // 未锁定或本模块不认识的字段没有被核对，也没有被应用
+    ServerSettingsConfirmed,
+}
+
+// 上游一键连接链接的格式是 `rustdesk://connection/new/{id}?password=...`；
+// 其它 rustdesk:// 链接（包括 `rustdesk://config?...` 查询串）一律按 Malformed 拒绝
+const CONNECT_URI_PREFIX: &str = "rustdesk://connection/new/";
+
+fn parse_connect_uri(rest: &str) -> Result<ImportedConnectRequest, ImportError> {
+    let (id_part, query) = match rest.split_once('?') {
+        Some((id_part, query)) => (id_part, query),
+        None => (rest, ""),
+    };
+    let id = url_decode(id_part);
+    if id.is_empty() {
+        return Err(ImportError::Malformed(format!("一键连接链接缺少id：{}", rest)));
+    }
+    let password = parse_query_pairs(query)
+        .into_iter()
+        .find(|(key, _)| key == "password")
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty());
+    Ok(ImportedConnectRequest { id, password })
+}
+
+// 从一条一键连接链接（`rustdesk://connection/new/{id}?password=...`）或一段
+// RustDesk2.toml 配置片段导入设置。
+// 两种输入的处理方式完全不同：链接里的id/密码是要传给连接管理器的连接请求，
+// 这里只解析、不在本模块内发起连接；而TOML片段里的服务器字段已经在编译时
+// 锁进了HARD_SETTINGS，这里只校验导入内容是否与锁定值一致，一致才放行——
+// 一旦试图覆盖锁定字段就拒绝，保留本模块存在的意义：服务器地址和密钥一旦
+// 编译进二进制，运行时谁都改不了。
+pub fn apply_imported_config(input: &str) -> Result<ImportedConfig, ImportError> {
+    if let Some(rest) = input.strip_prefix(CONNECT_URI_PREFIX) {
+        return parse_connect_uri(rest).map(ImportedConfig::Connect);
+    }
+    if input.starts_with("rustdesk://") {
+        return Err(ImportError::Malformed(format!("不支持的 rustdesk:// 链接：{}", input)));
+    }
+
+    let fields = parse_toml_fragment(input)?;
+    if fields.is_empty() {
+        return Err(ImportError::Malformed("导入内容中没有可识别的字段".to_string()));
+    }
+
+    let hard_settings = config::HARD_SETTINGS.read().unwrap();
+    for (field, value) in &fields {
+        let locked_key = match locked_settings_key_for(field) {
+            Some(k) => k,
+            None => continue, // 本模块不关心的字段，交给别的导入逻辑处理
+        };
+        match hard_settings.get(locked_key) {
+            Some(locked_value) if locked_value == value => {}
+            Some(_) if field == "key" => return Err(ImportError::KeyMismatch),
+            Some(_) => return Err(ImportError::LockedFieldOverride(field.clone())),
+            // locked_settings_key_for 认识这个字段，但编译时配置并没有把它锁进
+            // HARD_SETTINGS——比如没配置anchor_port时的nat-test-port/relay-server-ws-port、
+            // 没配置auto_disconnect_minutes时的auto-disconnect-timeout、access_mode为Custom
+            // 时的permission。这种情况下这个字段当前根本没被锁定，不算"试图覆盖锁定字段"，
+            // 放行交给别的导入逻辑处理，而不是无差别拒绝。
+            None => {}
+        }
+    }
+
+    Ok(ImportedConfig::ServerSettingsConfirmed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchor_ports_use_realistic_non_identical_defaults() {
+        // 21116/21117 是上游 hbbs/hbbr 的真实默认端口，两者相差1，不能相同
+        let ports = derive_anchor_ports(21116);
+        assert_eq!(ports.rendezvous, 21116);
+        assert_eq!(ports.nat_test, 21115);
+        assert_eq!(ports.relay, 21117);
+        assert_eq!(ports.web_socket, 21119);
+        assert_ne!(ports.rendezvous, ports.relay);
+    }
+
+    #[test]
+    fn anchor_port_saturates_instead_of_underflowing() {
+        let ports = derive_anchor_ports(0);
+        assert_eq!(ports.nat_test, 0);
+    }
+
+    #[test]
+    fn base64_decode_decodes_known_vector() {
+        // "hello" 的标准 Base64 编码是 "aGVsbG8="
+        assert_eq!(base64_decode("aGVsbG8=").unwrap(), b"hello".to_vec());
+    }
+
+    #[test]
+    fn base64_decode_rejects_invalid_characters() {
+        assert!(base64_decode("not valid base64!!").is_err());
+    }
+
+    #[test]
+    fn parse_server_list_splits_on_comma_and_newline_and_trims() {
+        let parsed = parse_server_list(" a:1, b:2\n c:3 \n\n");
+        assert_eq!(parsed, vec!["a:1".to_string(), "b:2".to_string(), "c:3".to_string()]);
+    }
+
+    #[test]
+    fn parse_server_list_preserves_order_with_primary_first() {
+        let parsed = parse_server_list("primary:1,backup:2");
+        assert_eq!(parsed.first(), Some(&"primary:1".to_string()));
+        assert_eq!(parsed.get(1..), Some(&["backup:2".to_string()][..]));
+    }
+
+    #[test]
+    fn url_decode_handles_percent_escapes_and_plus() {
+        assert_eq!(url_decode("hello%20world"), "hello world");
+        assert_eq!(url_decode("a+b"), "a b");
+        assert_eq!(url_decode("no-escapes"), "no-escapes");
+    }
+
+    #[test]
+    fn url_decode_keeps_invalid_percent_sequence_literal() {
+        assert_eq!(url_decode("100%off"), "100%off");
+    }
+
+    #[test]
+    fn parse_query_pairs_splits_and_decodes() {
+        let pairs = parse_query_pairs("password=a%26b&empty=&id=123");
+        assert_eq!(
+            pairs,
+            vec![
+                ("password".to_string(), "a&b".to_string()),
+                ("empty".to_string(), "".to_string()),
+                ("id".to_string(), "123".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_toml_fragment_skips_comments_sections_and_blank_lines() {
+        let fragment = "# comment\n[options]\nkey = \"abc\"\n\nrendezvous-server = \"1.2.3.4\"\n";
+        let fields = parse_toml_fragment(fragment).unwrap();
+        assert_eq!(
+            fields,
+            vec![
+                ("key".to_string(), "abc".to_string()),
+                ("rendezvous-server".to_string(), "1.2.3.4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_imported_config_parses_real_connect_uri_with_password() {
+        let parsed =
+            apply_imported_config("rustdesk://connection/new/123456789?password=hunter2").unwrap();
+        assert_eq!(
+            parsed,
+            ImportedConfig::Connect(ImportedConnectRequest {
+                id: "123456789".to_string(),
+                password: Some("hunter2".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn apply_imported_config_parses_connect_uri_without_password() {
+        let parsed = apply_imported_config("rustdesk://connection/new/123456789").unwrap();
+        assert_eq!(
+            parsed,
+            ImportedConfig::Connect(ImportedConnectRequest {
+                id: "123456789".to_string(),
+                password: None,
+            })
+        );
+    }
+
+    #[test]
+    fn apply_imported_config_rejects_unknown_rustdesk_uri() {
+        assert!(apply_imported_config("rustdesk://config?key=abc").is_err());
+    }
+
+    // HARD_SETTINGS 是 hbb_common::config 里的全局单例，也是 init_compile_time_config
+    // 在生产环境里锁设置用的同一份数据，cargo test 默认多线程跑同一个二进制，
+    // 直接写它会和其它读/写同一批键的用例产生脏数据或数据竞争——下面的锁和清理辅助函数
+    // 保证这几个用例互斥执行，并在结束后把自己写入的键恢复成调用前的样子
+    static HARD_SETTINGS_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    fn with_clean_hard_settings<R>(keys: &[&str], body: impl FnOnce() -> R) -> R {
+        let _guard = HARD_SETTINGS_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before: Vec<Option<String>> = {
+            let hs = config::HARD_SETTINGS.read().unwrap();
+            keys.iter().map(|k| hs.get(*k).cloned()).collect()
+        };
+        let result = body();
+        let mut hs = config::HARD_SETTINGS.write().unwrap();
+        for (key, prev) in keys.iter().zip(before.into_iter()) {
+            match prev {
+                Some(value) => {
+                    hs.insert(key.to_string(), value);
+                }
+                None => {
+                    hs.remove(*key);
+                }
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn apply_imported_config_confirms_when_toml_fields_match_locked_settings() {
+        let keys = [config::keys::OPTION_CUSTOM_RENDEZVOUS_SERVER, "encrypted-only"];
+        with_clean_hard_settings(&keys, || {
+            {
+                let mut hs = config::HARD_SETTINGS.write().unwrap();
+                hs.insert(
+                    config::keys::OPTION_CUSTOM_RENDEZVOUS_SERVER.to_string(),
+                    "1.2.3.4:21117".to_string(),
+                );
+                hs.insert("encrypted-only".to_string(), "Y".to_string());
+            }
+            let fragment = "rendezvous-server = \"1.2.3.4:21117\"\nencrypted-only = \"Y\"\n";
+            assert_eq!(apply_imported_config(fragment), Ok(ImportedConfig::ServerSettingsConfirmed));
+        });
+    }
+
+    #[test]
+    fn apply_imported_config_rejects_mismatched_locked_field() {
+        let keys = ["access-mode"];
+        with_clean_hard_settings(&keys, || {
+            {
+                let mut hs = config::HARD_SETTINGS.write().unwrap();
+                hs.insert("access-mode".to_string(), "full".to_string());
+            }
+            let fragment = "access-mode = \"custom\"\n";
+            assert_eq!(
+                apply_imported_config(fragment),
+                Err(ImportError::LockedFieldOverride("access-mode".to_string()))
+            );
+        });
+    }
+
+    #[test]
+    fn apply_imported_config_rejects_mismatched_permission() {
+        let keys = [config::keys::OPTION_PERMISSION];
+        with_clean_hard_settings(&keys, || {
+            {
+                let mut hs = config::HARD_SETTINGS.write().unwrap();
+                hs.insert(config::keys::OPTION_PERMISSION.to_string(), "all".to_string());
+            }
+            let fragment = "permission = \"read\"\n";
+            assert_eq!(
+                apply_imported_config(fragment),
+                Err(ImportError::LockedFieldOverride("permission".to_string()))
+            );
+        });
+    }
+
+    #[test]
+    fn apply_imported_config_accepts_field_not_currently_locked() {
+        // access_mode为Custom时permission不会被写进HARD_SETTINGS（见init_compile_time_config），
+        // auto-disconnect-timeout/nat-test-port/relay-server-ws-port同理在对应选项关闭时也不会被锁——
+        // 这种"认识这个字段、但当前没锁"的情况不该被当成"试图覆盖锁定字段"而无差别拒绝
+        let keys = [config::keys::OPTION_PERMISSION, "auto-disconnect-timeout"];
+        with_clean_hard_settings(&keys, || {
+            {
+                let mut hs = config::HARD_SETTINGS.write().unwrap();
+                hs.remove(config::keys::OPTION_PERMISSION);
+                hs.remove("auto-disconnect-timeout");
+            }
+            let fragment = "permission = \"all\"\nauto-disconnect-timeout = \"10\"\n";
+            assert_eq!(apply_imported_config(fragment), Ok(ImportedConfig::ServerSettingsConfirmed));
+        });
+    }
+
+    #[test]
+    fn apply_imported_config_rejects_key_mismatch() {
+        let keys = [config::keys::OPTION_KEY];
+        with_clean_hard_settings(&keys, || {
+            {
+                let mut hs = config::HARD_SETTINGS.write().unwrap();
+                hs.insert(config::keys::OPTION_KEY.to_string(), "bakedkey".to_string());
+            }
+            let fragment = "key = \"wrongkey\"\n";
+            assert_eq!(apply_imported_config(fragment), Err(ImportError::KeyMismatch));
+        });
+    }
 }
\ No newline at end of file